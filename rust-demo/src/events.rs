@@ -0,0 +1,31 @@
+use crate::idl::DecodedInstruction;
+use crate::solana_messages::{
+    BalanceUpdateMessage, DexOrderMessage, DexPoolMessage, DexTradeMessage, TransactionMessage,
+    TransferMessage,
+};
+
+/// A decoded message plus the stream it came from. Multiplexing several
+/// `stream_*` tasks onto one channel means the consumer needs this tag to
+/// tell trades from transfers from the same queue.
+#[derive(Debug)]
+pub struct TaggedEvent {
+    pub stream_type: &'static str,
+    pub payload: StreamPayload,
+    /// True when this message's slot is lower than one we've already seen
+    /// on this stream. Only meaningful under `processed` commitment, where
+    /// a later message can supersede an earlier one after a fork.
+    pub superseded: bool,
+}
+
+#[derive(Debug)]
+pub enum StreamPayload {
+    DexTrade(DexTradeMessage),
+    DexOrder(DexOrderMessage),
+    DexPool(DexPoolMessage),
+    /// The raw transaction plus one decoded instruction per entry in
+    /// `TransactionMessage.transaction.instructions` (`None` where no loaded
+    /// IDL matched the program/discriminator).
+    Transaction(TransactionMessage, Vec<Option<DecodedInstruction>>),
+    Transfer(TransferMessage),
+    Balance(BalanceUpdateMessage),
+}
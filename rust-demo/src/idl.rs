@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+use std::fs;
+
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::encode_base58;
+
+/// The handful of Anchor/Borsh field types this sample knows how to decode.
+/// Anchor IDLs carry far more (vecs, options, defined/struct types, fixed
+/// arrays, bytes...); `Unknown` covers all of those so a real-world IDL
+/// (which almost always has at least one) still loads, it just means any
+/// instruction using one of those args falls back to raw base58 at decode
+/// time rather than producing a garbled partial decode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdlType {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    I8,
+    I16,
+    I32,
+    I64,
+    String,
+    PublicKey,
+    Unknown,
+}
+
+impl IdlType {
+    fn from_value(value: &Value) -> IdlType {
+        match value.as_str() {
+            Some("bool") => IdlType::Bool,
+            Some("u8") => IdlType::U8,
+            Some("u16") => IdlType::U16,
+            Some("u32") => IdlType::U32,
+            Some("u64") => IdlType::U64,
+            Some("i8") => IdlType::I8,
+            Some("i16") => IdlType::I16,
+            Some("i32") => IdlType::I32,
+            Some("i64") => IdlType::I64,
+            Some("string") => IdlType::String,
+            // "publicKey" is the pre-0.30 spelling, "pubkey" the current one.
+            Some("publicKey") | Some("pubkey") => IdlType::PublicKey,
+            // Anything else (a complex type is a JSON object, not a string)
+            // is left as Unknown rather than rejected.
+            _ => IdlType::Unknown,
+        }
+    }
+}
+
+/// `type` can be a bare string (`"u64"`) or a JSON object describing a
+/// complex type (`{"vec": "u8"}`, `{"option": "u64"}`, `{"defined": "Foo"}`,
+/// `{"array": ["u8", 32]}`, ...). We only know how to decode the former, so
+/// parse it as a generic `Value` first and never fail the surrounding IDL
+/// over a type we don't recognize.
+fn deserialize_idl_type<'de, D>(deserializer: D) -> Result<IdlType, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Value::deserialize(deserializer)?;
+    Ok(IdlType::from_value(&value))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdlField {
+    pub name: String,
+    #[serde(rename = "type", deserialize_with = "deserialize_idl_type")]
+    pub ty: IdlType,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdlInstructionDef {
+    pub name: String,
+    #[serde(default)]
+    pub args: Vec<IdlField>,
+    /// Anchor 0.30+ IDLs spell the discriminator out explicitly instead of
+    /// leaving it to be derived from the name; honor it when present.
+    #[serde(default)]
+    pub discriminator: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Idl {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub instructions: Vec<IdlInstructionDef>,
+}
+
+/// An instruction matched against a loaded IDL and decoded into named,
+/// JSON-renderable arguments.
+#[derive(Debug)]
+pub struct DecodedInstruction {
+    pub program_name: String,
+    pub instruction_name: String,
+    pub args: Vec<(String, Value)>,
+}
+
+/// Anchor's discriminator is the first 8 bytes of
+/// `sha256("global:<snake_case_ix_name>")`. Pre-0.30 IDL JSON stores the
+/// instruction name in camelCase, so it has to be converted before hashing.
+fn discriminator_for(instruction_name: &str) -> [u8; 8] {
+    let snake_case_name = to_snake_case(instruction_name);
+    let mut hasher = Sha256::new();
+    hasher.update(format!("global:{snake_case_name}"));
+    let digest = hasher.finalize();
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&digest[..8]);
+    discriminator
+}
+
+/// The discriminator to match `data` against for `ix`: its explicit
+/// `discriminator` field when the IDL carries one, else the hash derived
+/// from its (snake_cased) name.
+fn expected_discriminator(ix: &IdlInstructionDef) -> [u8; 8] {
+    match &ix.discriminator {
+        Some(bytes) if bytes.len() == 8 => {
+            let mut discriminator = [0u8; 8];
+            discriminator.copy_from_slice(bytes);
+            discriminator
+        }
+        _ => discriminator_for(&ix.name),
+    }
+}
+
+/// Converts a camelCase (or already snake_case) identifier to snake_case,
+/// matching the normalization Anchor applies before hashing instruction
+/// names into discriminators.
+fn to_snake_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len() + 4);
+    for (i, ch) in name.chars().enumerate() {
+        if ch.is_uppercase() {
+            if i != 0 {
+                out.push('_');
+            }
+            out.extend(ch.to_lowercase());
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
+
+/// Loads every `*.json` Anchor IDL file in `dir`, keyed by the base58
+/// program address each IDL declares in its `metadata.address` (or
+/// top-level `address`, for older IDLs). A file that isn't valid JSON or
+/// doesn't parse as an IDL at all is skipped with a warning rather than
+/// aborting startup — one malformed file shouldn't take down every stream.
+pub fn load_idls(dir: &str) -> Result<HashMap<String, Idl>, Box<dyn std::error::Error>> {
+    let mut idls = HashMap::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) => {
+                eprintln!("[idl] skipping {}: {err}", path.display());
+                continue;
+            }
+        };
+        let raw: Value = match serde_json::from_str(&contents) {
+            Ok(raw) => raw,
+            Err(err) => {
+                eprintln!("[idl] skipping {}: invalid JSON: {err}", path.display());
+                continue;
+            }
+        };
+        let address = raw
+            .get("metadata")
+            .and_then(|m| m.get("address"))
+            .or_else(|| raw.get("address"))
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+        let Some(address) = address else {
+            eprintln!("[idl] skipping {}: no metadata.address or address field", path.display());
+            continue;
+        };
+        let idl: Idl = match serde_json::from_value(raw) {
+            Ok(idl) => idl,
+            Err(err) => {
+                eprintln!("[idl] skipping {}: {err}", path.display());
+                continue;
+            }
+        };
+        idls.insert(address, idl);
+    }
+    Ok(idls)
+}
+
+/// Matches `data`'s leading 8-byte discriminator against `program_id`'s IDL
+/// instructions and decodes the remainder using the Borsh layout implied by
+/// each arg's declared type. Returns `None` — meaning the raw base58 data
+/// should be printed instead — if the program has no loaded IDL, no
+/// instruction's discriminator matches, or the matched instruction has an
+/// arg type we don't know how to decode (continuing past it would read the
+/// rest of the args at the wrong offsets).
+pub fn decode_instruction(
+    idls: &HashMap<String, Idl>,
+    program_id: &[u8],
+    data: &[u8],
+) -> Option<DecodedInstruction> {
+    if data.len() < 8 {
+        return None;
+    }
+    let program_id_b58 = encode_base58(program_id);
+    let idl = idls.get(&program_id_b58)?;
+    let (discriminator, rest) = data.split_at(8);
+    let instruction = idl
+        .instructions
+        .iter()
+        .find(|ix| expected_discriminator(ix) == discriminator)?;
+
+    if instruction.args.iter().any(|field| field.ty == IdlType::Unknown) {
+        return None;
+    }
+
+    let mut cursor = rest;
+    let mut args = Vec::with_capacity(instruction.args.len());
+    for field in &instruction.args {
+        let value = decode_field(&field.ty, &mut cursor)?;
+        args.push((field.name.clone(), value));
+    }
+
+    Some(DecodedInstruction {
+        program_name: idl.name.clone(),
+        instruction_name: instruction.name.clone(),
+        args,
+    })
+}
+
+/// Reads one Borsh-encoded primitive off the front of `cursor`, advancing it.
+fn decode_field(ty: &IdlType, cursor: &mut &[u8]) -> Option<Value> {
+    match ty {
+        IdlType::Bool => take(cursor, 1).map(|b| Value::Bool(b[0] != 0)),
+        IdlType::U8 => take(cursor, 1).map(|b| Value::from(b[0])),
+        IdlType::I8 => take(cursor, 1).map(|b| Value::from(b[0] as i8)),
+        IdlType::U16 => take(cursor, 2).map(|b| Value::from(u16::from_le_bytes(b.try_into().unwrap()))),
+        IdlType::I16 => take(cursor, 2).map(|b| Value::from(i16::from_le_bytes(b.try_into().unwrap()))),
+        IdlType::U32 => take(cursor, 4).map(|b| Value::from(u32::from_le_bytes(b.try_into().unwrap()))),
+        IdlType::I32 => take(cursor, 4).map(|b| Value::from(i32::from_le_bytes(b.try_into().unwrap()))),
+        // u64/i64 are rendered as strings, same as the amount fields
+        // elsewhere in this sample, to avoid f64 precision loss in JSON.
+        IdlType::U64 => take(cursor, 8).map(|b| Value::String(u64::from_le_bytes(b.try_into().unwrap()).to_string())),
+        IdlType::I64 => take(cursor, 8).map(|b| Value::String(i64::from_le_bytes(b.try_into().unwrap()).to_string())),
+        IdlType::PublicKey => take(cursor, 32).map(|b| Value::String(encode_base58(b))),
+        IdlType::String => {
+            let len_bytes = take(cursor, 4)?;
+            let len = u32::from_le_bytes(len_bytes.try_into().unwrap()) as usize;
+            take(cursor, len).and_then(|b| String::from_utf8(b.to_vec()).ok()).map(Value::String)
+        }
+        IdlType::Unknown => None,
+    }
+}
+
+fn take<'a>(cursor: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+    if cursor.len() < len {
+        return None;
+    }
+    let (head, tail) = cursor.split_at(len);
+    *cursor = tail;
+    Some(head)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snake_cases_camel_case_instruction_names() {
+        assert_eq!(to_snake_case("initializeAccount"), "initialize_account");
+        assert_eq!(to_snake_case("swap"), "swap");
+    }
+
+    #[test]
+    fn decode_instruction_round_trips_a_matching_instruction() {
+        let program_id = vec![7u8; 32];
+        let program_id_b58 = encode_base58(&program_id);
+
+        let idl = Idl {
+            name: "example".to_string(),
+            instructions: vec![IdlInstructionDef {
+                name: "initializeAccount".to_string(),
+                args: vec![
+                    IdlField { name: "amount".to_string(), ty: IdlType::U64 },
+                    IdlField { name: "owner".to_string(), ty: IdlType::PublicKey },
+                ],
+                discriminator: None,
+            }],
+        };
+        let mut idls = HashMap::new();
+        idls.insert(program_id_b58, idl);
+
+        let owner = vec![9u8; 32];
+        let mut data = discriminator_for("initializeAccount").to_vec();
+        data.extend_from_slice(&42u64.to_le_bytes());
+        data.extend_from_slice(&owner);
+
+        let decoded = decode_instruction(&idls, &program_id, &data).expect("should decode");
+        assert_eq!(decoded.program_name, "example");
+        assert_eq!(decoded.instruction_name, "initializeAccount");
+        assert_eq!(decoded.args[0], ("amount".to_string(), Value::String("42".to_string())));
+        assert_eq!(decoded.args[1], ("owner".to_string(), Value::String(encode_base58(&owner))));
+    }
+
+    #[test]
+    fn decode_instruction_falls_back_on_unknown_arg_type() {
+        let program_id = vec![7u8; 32];
+        let program_id_b58 = encode_base58(&program_id);
+
+        let idl = Idl {
+            name: "example".to_string(),
+            instructions: vec![IdlInstructionDef {
+                name: "doSomething".to_string(),
+                args: vec![IdlField { name: "items".to_string(), ty: IdlType::Unknown }],
+                discriminator: None,
+            }],
+        };
+        let mut idls = HashMap::new();
+        idls.insert(program_id_b58, idl);
+
+        let mut data = discriminator_for("doSomething").to_vec();
+        data.extend_from_slice(&[1, 2, 3, 4]);
+
+        assert!(decode_instruction(&idls, &program_id, &data).is_none());
+    }
+}
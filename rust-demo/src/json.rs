@@ -0,0 +1,135 @@
+use serde_json::{json, Value};
+
+use crate::encode_base58;
+use crate::idl::DecodedInstruction;
+use crate::solana_messages::{
+    BalanceUpdateMessage, Block, DexOrderMessage, DexPoolMessage, DexTradeMessage,
+    TransactionMessage, TransferMessage,
+};
+
+/// Converts a decoded prost message into a JSON value suitable for NDJSON
+/// output, base58-encoding address/signature/mint bytes and leaving amounts
+/// as the strings the proto already carries them as (no f64 rounding).
+pub trait ToJson {
+    fn to_json(&self) -> Value;
+}
+
+fn block_json(block: &Option<Block>) -> Value {
+    match block {
+        Some(block) => json!({ "slot": block.slot, "hash": block.hash, "timestamp": block.timestamp }),
+        None => Value::Null,
+    }
+}
+
+impl ToJson for DexTradeMessage {
+    fn to_json(&self) -> Value {
+        json!({
+            "block": block_json(&self.block),
+            "trade": self.trade.as_ref().map(|trade| json!({
+                "dex_program": trade.dex.as_ref().map(|d| encode_base58(&d.program_address)),
+                "market_address": trade.market.as_ref().map(|m| encode_base58(&m.market_address)),
+                "trader": encode_base58(&trade.trader),
+                "base_amount": trade.base_amount,
+                "quote_amount": trade.quote_amount,
+                "price": trade.price,
+            })),
+        })
+    }
+}
+
+impl ToJson for DexOrderMessage {
+    fn to_json(&self) -> Value {
+        json!({
+            "block": block_json(&self.block),
+            "order": self.order.as_ref().map(|order| json!({
+                "dex_program": order.dex.as_ref().map(|d| encode_base58(&d.program_address)),
+                "market_address": order.market.as_ref().map(|m| encode_base58(&m.market_address)),
+                "trader": encode_base58(&order.trader),
+                "side": order.side,
+                "price": order.price,
+                "amount": order.amount,
+            })),
+        })
+    }
+}
+
+impl ToJson for DexPoolMessage {
+    fn to_json(&self) -> Value {
+        json!({
+            "block": block_json(&self.block),
+            "pool_event": self.pool_event.as_ref().map(|pool_event| json!({
+                "dex_program": pool_event.dex.as_ref().map(|d| encode_base58(&d.program_address)),
+                "market_address": pool_event.market.as_ref().map(|m| encode_base58(&m.market_address)),
+                "base_reserve": pool_event.base_reserve,
+                "quote_reserve": pool_event.quote_reserve,
+            })),
+        })
+    }
+}
+
+impl ToJson for TransactionMessage {
+    fn to_json(&self) -> Value {
+        transaction_json(self, &[])
+    }
+}
+
+/// Same shape as `TransactionMessage::to_json`, but with each instruction
+/// carrying its IDL-decoded name and args where `decoded` has a match
+/// (indices line up with `transaction.instructions`; a short or empty
+/// `decoded` slice just leaves the remaining instructions un-decoded).
+pub fn transaction_json(message: &TransactionMessage, decoded: &[Option<DecodedInstruction>]) -> Value {
+    json!({
+        "block": block_json(&message.block),
+        "transaction": message.transaction.as_ref().map(|transaction| json!({
+            "signature": encode_base58(&transaction.signature),
+            "signers": transaction.signers.iter().map(|s| encode_base58(s)).collect::<Vec<_>>(),
+            "success": transaction.success,
+            "instructions": transaction.instructions.iter().enumerate().map(|(i, ix)| {
+                let mut value = json!({
+                    "program_id": encode_base58(&ix.program_id),
+                    "accounts": ix.accounts.iter().map(|a| encode_base58(a)).collect::<Vec<_>>(),
+                    "data": encode_base58(&ix.data),
+                });
+                if let Some(Some(decoded)) = decoded.get(i) {
+                    if let Value::Object(map) = &mut value {
+                        map.insert("program_name".to_string(), json!(decoded.program_name));
+                        map.insert("instruction_name".to_string(), json!(decoded.instruction_name));
+                        map.insert(
+                            "args".to_string(),
+                            Value::Object(decoded.args.iter().cloned().collect()),
+                        );
+                    }
+                }
+                value
+            }).collect::<Vec<_>>(),
+        })),
+    })
+}
+
+impl ToJson for TransferMessage {
+    fn to_json(&self) -> Value {
+        json!({
+            "block": block_json(&self.block),
+            "transfer": self.transfer.as_ref().map(|transfer| json!({
+                "sender": transfer.sender.as_ref().map(|a| encode_base58(&a.address)),
+                "receiver": transfer.receiver.as_ref().map(|a| encode_base58(&a.address)),
+                "mint": transfer.currency.as_ref().map(|c| encode_base58(&c.mint_address)),
+                "amount": transfer.amount,
+            })),
+        })
+    }
+}
+
+impl ToJson for BalanceUpdateMessage {
+    fn to_json(&self) -> Value {
+        json!({
+            "block": block_json(&self.block),
+            "balance_update": self.balance_update.as_ref().map(|balance_update| json!({
+                "account": balance_update.account.as_ref().map(|a| encode_base58(&a.address)),
+                "mint": balance_update.currency.as_ref().map(|c| encode_base58(&c.mint_address)),
+                "pre_balance": balance_update.pre_balance,
+                "post_balance": balance_update.post_balance,
+            })),
+        })
+    }
+}
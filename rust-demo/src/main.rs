@@ -1,9 +1,20 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc;
 use tonic::metadata::MetadataValue;
 use tonic::transport::Channel;
 use bs58;
 
+mod events;
+mod idl;
+mod json;
+mod metrics;
+mod reconnect;
+
 pub mod solana_corecast {
     tonic::include_proto!("solana_corecast");
 }
@@ -18,12 +29,86 @@ use solana_corecast::{
     SubscribeTransactionsRequest, SubscribeTransfersRequest, SubscribeBalanceUpdateRequest,
     AddressFilter
 };
+pub use events::{StreamPayload, TaggedEvent};
+use idl::Idl;
+use json::ToJson;
+use metrics::Metrics;
+use reconnect::ReconnectConfig;
 
 #[derive(Debug, Deserialize, Serialize)]
 struct Config {
     server: ServerConfig,
     stream: StreamConfig,
     filters: FiltersConfig,
+    #[serde(default)]
+    reconnect: Option<ReconnectConfig>,
+    #[serde(default)]
+    output: OutputConfig,
+    #[serde(default)]
+    metrics: Option<MetricsConfig>,
+    #[serde(default)]
+    idl: Option<IdlConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct IdlConfig {
+    /// Directory of Anchor IDL JSON files, keyed by the program address
+    /// each declares in `metadata.address`.
+    dir: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct MetricsConfig {
+    #[serde(default = "default_metrics_enabled")]
+    enabled: bool,
+    #[serde(default = "default_metrics_interval_secs")]
+    interval_secs: u64,
+    #[serde(default)]
+    listen: Option<String>,
+}
+
+fn default_metrics_enabled() -> bool {
+    true
+}
+
+fn default_metrics_interval_secs() -> u64 {
+    10
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        MetricsConfig {
+            enabled: default_metrics_enabled(),
+            interval_secs: default_metrics_interval_secs(),
+            listen: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct OutputConfig {
+    #[serde(default)]
+    format: OutputFormat,
+    path: Option<String>,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        OutputConfig { format: OutputFormat::Text, path: None }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum OutputFormat {
+    Text,
+    Ndjson,
+}
+
+impl Default for OutputFormat {
+    fn default() -> Self {
+        OutputFormat::Text
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -35,30 +120,131 @@ struct ServerConfig {
 
 #[derive(Debug, Deserialize, Serialize)]
 struct StreamConfig {
-    #[serde(rename = "type")]
-    stream_type: String,
+    #[serde(rename = "types")]
+    stream_types: Vec<String>,
+    #[serde(default)]
+    commitment: CommitmentLevel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum CommitmentLevel {
+    Processed,
+    Confirmed,
+    Finalized,
+}
+
+impl Default for CommitmentLevel {
+    fn default() -> Self {
+        CommitmentLevel::Confirmed
+    }
+}
+
+impl From<CommitmentLevel> for solana_corecast::Commitment {
+    fn from(level: CommitmentLevel) -> Self {
+        match level {
+            CommitmentLevel::Processed => solana_corecast::Commitment::Processed,
+            CommitmentLevel::Confirmed => solana_corecast::Commitment::Confirmed,
+            CommitmentLevel::Finalized => solana_corecast::Commitment::Finalized,
+        }
+    }
 }
 
+const KNOWN_STREAM_TYPES: &[&str] = &[
+    "dex_trades",
+    "dex_orders",
+    "dex_pools",
+    "transactions",
+    "transfers",
+    "balances",
+];
+
 #[derive(Debug, Deserialize, Serialize)]
 struct FiltersConfig {
-    programs: Option<Vec<String>>,
-    pools: Option<Vec<String>>,
-    tokens: Option<Vec<String>>,
-    traders: Option<Vec<String>>,
-    senders: Option<Vec<String>>,
-    receivers: Option<Vec<String>>,
-    addresses: Option<Vec<String>>,
-    signers: Option<Vec<String>>,
+    programs: Option<FilterSpec>,
+    pools: Option<FilterSpec>,
+    tokens: Option<FilterSpec>,
+    traders: Option<FilterSpec>,
+    senders: Option<FilterSpec>,
+    receivers: Option<FilterSpec>,
+    addresses: Option<FilterSpec>,
+    signers: Option<FilterSpec>,
+}
+
+impl FiltersConfig {
+    /// Every named field, paired with its YAML key, for validation and
+    /// error-reporting purposes.
+    fn fields(&self) -> [(&'static str, &Option<FilterSpec>); 8] {
+        [
+            ("programs", &self.programs),
+            ("pools", &self.pools),
+            ("tokens", &self.tokens),
+            ("traders", &self.traders),
+            ("senders", &self.senders),
+            ("receivers", &self.receivers),
+            ("addresses", &self.addresses),
+            ("signers", &self.signers),
+        ]
+    }
+}
+
+/// One field's filter: an include allow-list, an exclude deny-list (checked
+/// first), and how `include` combines when a message carries more than one
+/// candidate address for the field (e.g. a transaction's signers).
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+struct FilterSpec {
+    #[serde(default)]
+    include: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+    #[serde(default)]
+    mode: MatchMode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum MatchMode {
+    #[default]
+    Any,
+    All,
+}
+
+impl From<MatchMode> for solana_corecast::MatchMode {
+    fn from(mode: MatchMode) -> Self {
+        match mode {
+            MatchMode::Any => solana_corecast::MatchMode::Any,
+            MatchMode::All => solana_corecast::MatchMode::All,
+        }
+    }
 }
 
 fn load_config() -> Result<Config, Box<dyn std::error::Error>> {
     let config_content = fs::read_to_string("src/config.yaml")?;
     let config: Config = serde_yaml::from_str(&config_content)?;
+    validate_filters(&config.filters)?;
     Ok(config)
 }
 
-fn create_address_filter(addresses: Option<Vec<String>>) -> Option<AddressFilter> {
-    addresses.map(|addrs| AddressFilter { addresses: addrs })
+/// Fails fast on a malformed address rather than letting it reach the
+/// server as silent garbage that never matches anything.
+fn validate_filters(filters: &FiltersConfig) -> Result<(), Box<dyn std::error::Error>> {
+    for (name, spec) in filters.fields() {
+        let Some(spec) = spec else { continue };
+        for address in spec.include.iter().chain(spec.exclude.iter()) {
+            if bs58::decode(address).into_vec().is_err() {
+                return Err(format!("filters.{name}: invalid base58 address {address:?}").into());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn create_address_filter(spec: Option<FilterSpec>) -> Option<AddressFilter> {
+    spec.map(|spec| AddressFilter {
+        include: spec.include,
+        exclude: spec.exclude,
+        mode: solana_corecast::MatchMode::from(spec.mode) as i32,
+    })
 }
 
 fn encode_base58(bytes: &[u8]) -> String {
@@ -73,220 +259,413 @@ fn add_auth_header<T>(mut request: tonic::Request<T>, config: &Config) -> Result
     Ok(request)
 }
 
-async fn stream_dex_trades(client: &mut CoreCastClient<Channel>, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+async fn stream_dex_trades(client: &mut CoreCastClient<Channel>, config: &Config, from_slot: u64, last_slot: &AtomicU64, tx: &mpsc::Sender<TaggedEvent>, metrics: &Arc<Metrics>) -> Result<(), Box<dyn std::error::Error>> {
     println!("Subscribing to DEX trades...");
-    
+
     let request = SubscribeTradesRequest {
         program: create_address_filter(config.filters.programs.clone()),
         pool: create_address_filter(config.filters.pools.clone()),
         token: create_address_filter(config.filters.tokens.clone()),
         trader: create_address_filter(config.filters.traders.clone()),
+        from_slot,
+        commitment: solana_corecast::Commitment::from(config.stream.commitment) as i32,
     };
-    
+
     let grpc_request = add_auth_header(tonic::Request::new(request), config)?;
     let mut stream = client.dex_trades(grpc_request).await?.into_inner();
-    
+
     println!("Streaming DEX trades. Processing first message...");
-    
+
     while let Some(message) = stream.message().await? {
-        println!("Received DEX trade message:");
-        println!("  Block Slot: {:?}", message.block.map(|b| b.slot));
-        if let Some(trade) = &message.trade {
-            if let Some(dex) = &trade.dex {
-                println!("  Trade Program: {}", encode_base58(&dex.program_address));
-            }
-            if let Some(market) = &trade.market {
-                println!("  Trade Market: {}", encode_base58(&market.market_address));
-            }
+        let superseded = if let Some(block) = &message.block {
+            metrics.record_message("dex_trades", block.slot);
+            let previous = last_slot.fetch_max(block.slot, Ordering::Relaxed);
+            config.stream.commitment == CommitmentLevel::Processed && block.slot < previous
+        } else {
+            false
+        };
+        if tx.send(TaggedEvent { stream_type: "dex_trades", payload: StreamPayload::DexTrade(message), superseded }).await.is_err() {
+            break;
         }
-        // continue streaming
     }
-    
+
     Ok(())
 }
 
-async fn stream_dex_orders(client: &mut CoreCastClient<Channel>, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+async fn stream_dex_orders(client: &mut CoreCastClient<Channel>, config: &Config, from_slot: u64, last_slot: &AtomicU64, tx: &mpsc::Sender<TaggedEvent>, metrics: &Arc<Metrics>) -> Result<(), Box<dyn std::error::Error>> {
     println!("Subscribing to DEX orders...");
-    
+
     let request = SubscribeOrdersRequest {
         program: create_address_filter(config.filters.programs.clone()),
         pool: create_address_filter(config.filters.pools.clone()),
         token: create_address_filter(config.filters.tokens.clone()),
         trader: create_address_filter(config.filters.traders.clone()),
+        from_slot,
+        commitment: solana_corecast::Commitment::from(config.stream.commitment) as i32,
     };
-    
+
     let grpc_request = add_auth_header(tonic::Request::new(request), config)?;
     let mut stream = client.dex_orders(grpc_request).await?.into_inner();
-    
+
     println!("Streaming DEX orders. Processing first message...");
-    
+
     while let Some(message) = stream.message().await? {
-        println!("Received DEX order message:");
-        println!("  Block Slot: {:?}", message.block.map(|b| b.slot));
-       
-        if let Some(order) = &message.order {
-            if let Some(dex) = &order.dex {
-                println!("  Order Program: {}", encode_base58(&dex.program_address));
-            }
+        let superseded = if let Some(block) = &message.block {
+            metrics.record_message("dex_orders", block.slot);
+            let previous = last_slot.fetch_max(block.slot, Ordering::Relaxed);
+            config.stream.commitment == CommitmentLevel::Processed && block.slot < previous
+        } else {
+            false
+        };
+        if tx.send(TaggedEvent { stream_type: "dex_orders", payload: StreamPayload::DexOrder(message), superseded }).await.is_err() {
+            break;
         }
-        // continue streaming
     }
-    
+
     Ok(())
 }
 
-async fn stream_dex_pools(client: &mut CoreCastClient<Channel>, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+async fn stream_dex_pools(client: &mut CoreCastClient<Channel>, config: &Config, from_slot: u64, last_slot: &AtomicU64, tx: &mpsc::Sender<TaggedEvent>, metrics: &Arc<Metrics>) -> Result<(), Box<dyn std::error::Error>> {
     println!("Subscribing to DEX pools...");
-    
+
     let request = SubscribePoolsRequest {
         program: create_address_filter(config.filters.programs.clone()),
         pool: create_address_filter(config.filters.pools.clone()),
         token: create_address_filter(config.filters.tokens.clone()),
+        from_slot,
+        commitment: solana_corecast::Commitment::from(config.stream.commitment) as i32,
     };
-    
+
     let grpc_request = add_auth_header(tonic::Request::new(request), config)?;
     let mut stream = client.dex_pools(grpc_request).await?.into_inner();
-    
+
     println!("Streaming DEX pools. Processing first message...");
-    
+
     while let Some(message) = stream.message().await? {
-        println!("Received DEX pool message:");
-        println!("  Block Slot: {:?}", message.block.map(|b| b.slot));
-       
-        if let Some(pool_event) = &message.pool_event {
-            if let Some(dex) = &pool_event.dex {
-                println!("  Pool Event Program: {}", encode_base58(&dex.program_address));
-            }
+        let superseded = if let Some(block) = &message.block {
+            metrics.record_message("dex_pools", block.slot);
+            let previous = last_slot.fetch_max(block.slot, Ordering::Relaxed);
+            config.stream.commitment == CommitmentLevel::Processed && block.slot < previous
+        } else {
+            false
+        };
+        if tx.send(TaggedEvent { stream_type: "dex_pools", payload: StreamPayload::DexPool(message), superseded }).await.is_err() {
+            break;
         }
-        // continue streaming
     }
-    
+
     Ok(())
 }
 
-async fn stream_transactions(client: &mut CoreCastClient<Channel>, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+async fn stream_transactions(client: &mut CoreCastClient<Channel>, config: &Config, from_slot: u64, last_slot: &AtomicU64, tx: &mpsc::Sender<TaggedEvent>, metrics: &Arc<Metrics>, idls: &HashMap<String, Idl>) -> Result<(), Box<dyn std::error::Error>> {
     println!("Subscribing to transactions...");
-    
+
     let request = SubscribeTransactionsRequest {
         program: create_address_filter(config.filters.programs.clone()),
         signer: create_address_filter(config.filters.signers.clone()),
+        from_slot,
+        commitment: solana_corecast::Commitment::from(config.stream.commitment) as i32,
     };
-    
+
     let grpc_request = add_auth_header(tonic::Request::new(request), config)?;
     let mut stream = client.transactions(grpc_request).await?.into_inner();
-    
+
     println!("Streaming transactions. Processing first message...");
-    
+
     while let Some(message) = stream.message().await? {
-        println!("Received transaction message:");
-        println!("  Block Slot: {:?}", message.block.map(|b| b.slot));
-        println!("  Transaction Signature: {:?}", message.transaction.map(|t| t.signature));
-        // continue streaming
+        let superseded = if let Some(block) = &message.block {
+            metrics.record_message("transactions", block.slot);
+            let previous = last_slot.fetch_max(block.slot, Ordering::Relaxed);
+            config.stream.commitment == CommitmentLevel::Processed && block.slot < previous
+        } else {
+            false
+        };
+        let decoded = message.transaction.as_ref().map_or_else(Vec::new, |transaction| {
+            transaction
+                .instructions
+                .iter()
+                .map(|ix| idl::decode_instruction(idls, &ix.program_id, &ix.data))
+                .collect()
+        });
+        if tx.send(TaggedEvent { stream_type: "transactions", payload: StreamPayload::Transaction(message, decoded), superseded }).await.is_err() {
+            break;
+        }
     }
-    
+
     Ok(())
 }
 
-async fn stream_transfers(client: &mut CoreCastClient<Channel>, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+async fn stream_transfers(client: &mut CoreCastClient<Channel>, config: &Config, from_slot: u64, last_slot: &AtomicU64, tx: &mpsc::Sender<TaggedEvent>, metrics: &Arc<Metrics>) -> Result<(), Box<dyn std::error::Error>> {
     println!("Subscribing to transfers...");
-    
+
     let request = SubscribeTransfersRequest {
         sender: create_address_filter(config.filters.senders.clone()),
         receiver: create_address_filter(config.filters.receivers.clone()),
         token: create_address_filter(config.filters.tokens.clone()),
+        from_slot,
+        commitment: solana_corecast::Commitment::from(config.stream.commitment) as i32,
     };
-    
+
     let grpc_request = add_auth_header(tonic::Request::new(request), config)?;
     let mut stream = client.transfers(grpc_request).await?.into_inner();
-    
+
     println!("Streaming transfers. Processing first message...");
-    
+
     while let Some(message) = stream.message().await? {
-        println!("Received transfer message:");
-        println!("  Block Slot: {:?}", message.block.map(|b| b.slot));
-       
-        if let Some(transfer) = &message.transfer {
-            if let Some(sender) = &transfer.sender {
-                println!("  Transfer Sender: {}", encode_base58(&sender.address));
-            }
-            if let Some(receiver) = &transfer.receiver {
-                println!("  Transfer Receiver: {}", encode_base58(&receiver.address));
-            }
+        let superseded = if let Some(block) = &message.block {
+            metrics.record_message("transfers", block.slot);
+            let previous = last_slot.fetch_max(block.slot, Ordering::Relaxed);
+            config.stream.commitment == CommitmentLevel::Processed && block.slot < previous
+        } else {
+            false
+        };
+        if tx.send(TaggedEvent { stream_type: "transfers", payload: StreamPayload::Transfer(message), superseded }).await.is_err() {
+            break;
         }
-        // continue streaming
     }
-    
+
     Ok(())
 }
 
-async fn stream_balances(client: &mut CoreCastClient<Channel>, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+async fn stream_balances(client: &mut CoreCastClient<Channel>, config: &Config, from_slot: u64, last_slot: &AtomicU64, tx: &mpsc::Sender<TaggedEvent>, metrics: &Arc<Metrics>) -> Result<(), Box<dyn std::error::Error>> {
     println!("Subscribing to balances...");
-    
+
     let request = SubscribeBalanceUpdateRequest {
         address: create_address_filter(config.filters.addresses.clone()),
         token: create_address_filter(config.filters.tokens.clone()),
+        from_slot,
+        commitment: solana_corecast::Commitment::from(config.stream.commitment) as i32,
     };
-    
+
     let grpc_request = add_auth_header(tonic::Request::new(request), config)?;
     let mut stream = client.balances(grpc_request).await?.into_inner();
-    
+
     println!("Streaming balances. Processing first message...");
-    
+
     while let Some(message) = stream.message().await? {
-        println!("Received balance message:");
-        println!("  Block Slot: {:?}", message.block.map(|b| b.slot));
-       
-        if let Some(balance_update) = &message.balance_update {
-            if let Some(currency) = &balance_update.currency {
-                println!("  Balance Token: {}", encode_base58(&currency.mint_address));
-            }
+        let superseded = if let Some(block) = &message.block {
+            metrics.record_message("balances", block.slot);
+            let previous = last_slot.fetch_max(block.slot, Ordering::Relaxed);
+            config.stream.commitment == CommitmentLevel::Processed && block.slot < previous
+        } else {
+            false
+        };
+        if tx.send(TaggedEvent { stream_type: "balances", payload: StreamPayload::Balance(message), superseded }).await.is_err() {
+            break;
         }
-        println!("First message processed. Exiting.");
-        break;
     }
-    
+
     Ok(())
 }
 
+/// Prints one multiplexed event the way each `stream_*` function used to
+/// print inline, tagged with the stream it came from.
+fn print_event(event: &TaggedEvent) {
+    let tag = event.stream_type;
+    if event.superseded {
+        println!("[{tag}] (superseded: slot is behind one already seen on this stream, dedupe on slot)");
+    }
+    match &event.payload {
+        StreamPayload::DexTrade(message) => {
+            println!("[{tag}] Block Slot: {:?}", message.block.as_ref().map(|b| b.slot));
+            if let Some(trade) = &message.trade {
+                if let Some(dex) = &trade.dex {
+                    println!("[{tag}]   Trade Program: {}", encode_base58(&dex.program_address));
+                }
+                if let Some(market) = &trade.market {
+                    println!("[{tag}]   Trade Market: {}", encode_base58(&market.market_address));
+                }
+            }
+        }
+        StreamPayload::DexOrder(message) => {
+            println!("[{tag}] Block Slot: {:?}", message.block.as_ref().map(|b| b.slot));
+            if let Some(order) = &message.order {
+                if let Some(dex) = &order.dex {
+                    println!("[{tag}]   Order Program: {}", encode_base58(&dex.program_address));
+                }
+            }
+        }
+        StreamPayload::DexPool(message) => {
+            println!("[{tag}] Block Slot: {:?}", message.block.as_ref().map(|b| b.slot));
+            if let Some(pool_event) = &message.pool_event {
+                if let Some(dex) = &pool_event.dex {
+                    println!("[{tag}]   Pool Event Program: {}", encode_base58(&dex.program_address));
+                }
+            }
+        }
+        StreamPayload::Transaction(message, decoded) => {
+            println!("[{tag}] Block Slot: {:?}", message.block.as_ref().map(|b| b.slot));
+            println!("[{tag}]   Transaction Signature: {:?}", message.transaction.as_ref().map(|t| &t.signature));
+            if let Some(transaction) = &message.transaction {
+                for (ix, entry) in transaction.instructions.iter().zip(decoded.iter()) {
+                    match entry {
+                        Some(entry) => {
+                            let args = entry
+                                .args
+                                .iter()
+                                .map(|(name, value)| format!("{name}={value}"))
+                                .collect::<Vec<_>>()
+                                .join(", ");
+                            println!("[{tag}]   Instruction: {}.{} ({args})", entry.program_name, entry.instruction_name);
+                        }
+                        None => {
+                            println!("[{tag}]   Instruction: (undecoded) data={}", encode_base58(&ix.data));
+                        }
+                    }
+                }
+            }
+        }
+        StreamPayload::Transfer(message) => {
+            println!("[{tag}] Block Slot: {:?}", message.block.as_ref().map(|b| b.slot));
+            if let Some(transfer) = &message.transfer {
+                if let Some(sender) = &transfer.sender {
+                    println!("[{tag}]   Transfer Sender: {}", encode_base58(&sender.address));
+                }
+                if let Some(receiver) = &transfer.receiver {
+                    println!("[{tag}]   Transfer Receiver: {}", encode_base58(&receiver.address));
+                }
+            }
+        }
+        StreamPayload::Balance(message) => {
+            println!("[{tag}] Block Slot: {:?}", message.block.as_ref().map(|b| b.slot));
+            if let Some(balance_update) = &message.balance_update {
+                if let Some(currency) = &balance_update.currency {
+                    println!("[{tag}]   Balance Token: {}", encode_base58(&currency.mint_address));
+                }
+            }
+        }
+    }
+}
+
+/// Flattens a tagged event into the JSON object written for one NDJSON
+/// line: the decoded payload's fields plus the stream tag and supersede bit.
+fn event_to_json(event: &TaggedEvent) -> serde_json::Value {
+    let mut value = match &event.payload {
+        StreamPayload::DexTrade(message) => message.to_json(),
+        StreamPayload::DexOrder(message) => message.to_json(),
+        StreamPayload::DexPool(message) => message.to_json(),
+        StreamPayload::Transaction(message, decoded) => json::transaction_json(message, decoded),
+        StreamPayload::Transfer(message) => message.to_json(),
+        StreamPayload::Balance(message) => message.to_json(),
+    };
+    if let serde_json::Value::Object(map) = &mut value {
+        map.insert("stream_type".to_string(), serde_json::Value::String(event.stream_type.to_string()));
+        map.insert("superseded".to_string(), serde_json::Value::Bool(event.superseded));
+    }
+    value
+}
+
+/// Opens the configured output sink: a file when `output.path` is set
+/// (appended to, so re-running doesn't clobber a replay log), stdout
+/// otherwise.
+fn open_output(output: &OutputConfig) -> Result<Box<dyn Write + Send>, Box<dyn std::error::Error>> {
+    match &output.path {
+        Some(path) => {
+            let file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+            Ok(Box::new(file))
+        }
+        None => Ok(Box::new(std::io::stdout())),
+    }
+}
+
+/// Builds a `Channel` with the window/keep-alive tuning this sample always
+/// uses. Pulled out of `main` so the reconnect supervisor can call it again
+/// after a dropped connection.
+async fn build_channel(server_url: &str) -> Result<Channel, Box<dyn std::error::Error>> {
+    let channel = Channel::from_shared(server_url.to_string())?
+        .initial_stream_window_size(Some(16 * 1024 * 1024))  // 16MB
+        .initial_connection_window_size(Some(128 * 1024 * 1024))  // 128MB
+        .keep_alive_timeout(std::time::Duration::from_secs(5))
+        .keep_alive_while_idle(true)
+        .connect()
+        .await?;
+    Ok(channel)
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Load configuration from YAML file
     let config = load_config()?;
     println!("Loaded config:");
     println!("  Server: {} (insecure: {})", config.server.address, config.server.insecure);
-    println!("  Stream type: {}", config.stream.stream_type);
+    println!("  Stream types: {:?}", config.stream.stream_types);
+    println!("  Commitment: {:?}", config.stream.commitment);
+    println!("  Output: {:?} ({:?})", config.output.format, config.output.path.as_deref().unwrap_or("stdout"));
     println!("  Has authorization: {}", !config.server.authorization.is_empty());
 
+    if config.stream.stream_types.is_empty() {
+        eprintln!("No stream types configured. Supported types: {}", KNOWN_STREAM_TYPES.join("|"));
+        std::process::exit(1);
+    }
+    for stream_type in &config.stream.stream_types {
+        if !KNOWN_STREAM_TYPES.contains(&stream_type.as_str()) {
+            eprintln!("Unknown stream type: {}. Supported types: {}", stream_type, KNOWN_STREAM_TYPES.join("|"));
+            std::process::exit(1);
+        }
+    }
+
     // Build server URL based on config
     let protocol = if config.server.insecure { "http" } else { "https" };
     let server_url = format!("{}://{}", protocol, config.server.address);
     println!("Connecting to: {}", server_url);
 
-    // Create channel with options for better performance (similar to Python client)
-    let channel = Channel::from_shared(server_url)?
-        .initial_stream_window_size(Some(16 * 1024 * 1024))  // 16MB
-        .initial_connection_window_size(Some(128 * 1024 * 1024))  // 128MB
-        .keep_alive_timeout(std::time::Duration::from_secs(5))
-        .keep_alive_while_idle(true)
-        .connect()
-        .await?;
+    let config = Arc::new(config);
+    let (tx, mut rx) = mpsc::channel::<TaggedEvent>(1024);
 
-    let mut client = CoreCastClient::new(channel);
-
-    // Start streaming based on configuration
-    match config.stream.stream_type.as_str() {
-        "dex_trades" => stream_dex_trades(&mut client, &config).await?,
-        "dex_orders" => stream_dex_orders(&mut client, &config).await?,
-        "dex_pools" => stream_dex_pools(&mut client, &config).await?,
-        "transactions" => stream_transactions(&mut client, &config).await?,
-        "transfers" => stream_transfers(&mut client, &config).await?,
-        "balances" => stream_balances(&mut client, &config).await?,
-        _ => {
-            eprintln!("Unknown stream type: {}. Supported types: dex_trades|dex_orders|dex_pools|transactions|transfers|balances", 
-                     config.stream.stream_type);
-            std::process::exit(1);
+    let idls = match &config.idl {
+        Some(idl_config) => {
+            let idls = idl::load_idls(&idl_config.dir)?;
+            println!("  Loaded {} IDL(s) from {}", idls.len(), idl_config.dir);
+            Arc::new(idls)
+        }
+        None => Arc::new(HashMap::new()),
+    };
+
+    let metrics_config = config.metrics.clone().unwrap_or_default();
+    let metrics = Metrics::new(&config.stream.stream_types);
+    if metrics_config.enabled {
+        metrics::spawn_periodic_logger(metrics.clone(), std::time::Duration::from_secs(metrics_config.interval_secs));
+        if let Some(listen) = metrics_config.listen.clone() {
+            let metrics = metrics.clone();
+            tokio::spawn(async move {
+                if let Err(err) = metrics::serve_http(metrics, listen).await {
+                    eprintln!("[metrics] http server error: {err}");
+                }
+            });
         }
     }
 
+    let mut handles = Vec::new();
+    for stream_type in config.stream.stream_types.clone() {
+        let config = config.clone();
+        let server_url = server_url.clone();
+        let tx = tx.clone();
+        let metrics = metrics.clone();
+        let idls = idls.clone();
+        handles.push(tokio::spawn(async move {
+            if let Err(err) = reconnect::run_with_resume(&stream_type, &server_url, &config, tx, metrics, idls).await {
+                eprintln!("[{stream_type}] fatal: {err}");
+            }
+        }));
+    }
+    // Drop the original sender so `rx` closes once every spawned task (each
+    // holding its own clone) has finished.
+    drop(tx);
+
+    let mut output_writer = open_output(&config.output)?;
+    while let Some(event) = rx.recv().await {
+        match config.output.format {
+            OutputFormat::Text => print_event(&event),
+            OutputFormat::Ndjson => {
+                let line = serde_json::to_string(&event_to_json(&event))?;
+                writeln!(output_writer, "{line}")?;
+            }
+        }
+    }
+
+    for handle in handles {
+        let _ = handle.await;
+    }
+
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::net::TcpListener;
+
+/// Exponential histogram buckets, in milliseconds: 1, 2, 4, ..., 16384 (~16s),
+/// plus a final overflow bucket for anything slower than that.
+const BUCKET_BOUNDS_MS: [u64; 15] = [
+    1, 2, 4, 8, 16, 32, 64, 128, 256, 512, 1024, 2048, 4096, 8192, 16384,
+];
+const BUCKET_COUNT: usize = BUCKET_BOUNDS_MS.len() + 1;
+
+fn bucket_index(gap_ms: u64) -> usize {
+    BUCKET_BOUNDS_MS
+        .iter()
+        .position(|&bound| gap_ms <= bound)
+        .unwrap_or(BUCKET_BOUNDS_MS.len())
+}
+
+/// Per-stream counters. All fields are plain atomics so recording a message
+/// never blocks a concurrent reader (the periodic logger or the `/metrics`
+/// HTTP handler).
+pub struct StreamMetrics {
+    messages: AtomicU64,
+    last_reported_messages: AtomicU64,
+    newest_slot: AtomicU64,
+    last_seen_ms: AtomicU64,
+    histogram: [AtomicU64; BUCKET_COUNT],
+}
+
+impl StreamMetrics {
+    fn new() -> Self {
+        StreamMetrics {
+            messages: AtomicU64::new(0),
+            last_reported_messages: AtomicU64::new(0),
+            newest_slot: AtomicU64::new(0),
+            last_seen_ms: AtomicU64::new(0),
+            histogram: std::array::from_fn(|_| AtomicU64::new(0)),
+        }
+    }
+
+    /// Approximates the p-th percentile (0.0..=1.0) of inter-message gaps
+    /// from the bucket counts, returning the upper bound (in ms) of the
+    /// bucket the percentile falls into.
+    fn percentile_ms(&self, p: f64) -> u64 {
+        let counts: Vec<u64> = self.histogram.iter().map(|b| b.load(Ordering::Relaxed)).collect();
+        let total: u64 = counts.iter().sum();
+        if total == 0 {
+            return 0;
+        }
+        let target = ((total as f64) * p).ceil() as u64;
+        let mut cumulative = 0u64;
+        for (i, count) in counts.iter().enumerate() {
+            cumulative += count;
+            if cumulative >= target {
+                return *BUCKET_BOUNDS_MS.get(i).unwrap_or(&(BUCKET_BOUNDS_MS[BUCKET_BOUNDS_MS.len() - 1] * 2));
+            }
+        }
+        BUCKET_BOUNDS_MS[BUCKET_BOUNDS_MS.len() - 1] * 2
+    }
+}
+
+/// Shared handle for recording and reporting throughput/latency across all
+/// active streams. One instance is created in `main` and cloned (via `Arc`)
+/// into each stream task, the periodic logger, and the `/metrics` server.
+pub struct Metrics {
+    start: Instant,
+    streams: HashMap<String, StreamMetrics>,
+}
+
+impl Metrics {
+    pub fn new(stream_types: &[String]) -> Arc<Metrics> {
+        let streams = stream_types
+            .iter()
+            .map(|stream_type| (stream_type.clone(), StreamMetrics::new()))
+            .collect();
+        Arc::new(Metrics { start: Instant::now(), streams })
+    }
+
+    pub fn record_message(&self, stream_type: &str, slot: u64) {
+        let Some(stream) = self.streams.get(stream_type) else {
+            return;
+        };
+        stream.messages.fetch_add(1, Ordering::Relaxed);
+        stream.newest_slot.fetch_max(slot, Ordering::Relaxed);
+
+        let now_ms = self.start.elapsed().as_millis() as u64;
+        let previous = stream.last_seen_ms.swap(now_ms, Ordering::Relaxed);
+        if previous != 0 {
+            let gap_ms = now_ms.saturating_sub(previous);
+            stream.histogram[bucket_index(gap_ms)].fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Logs one summary line per active stream: throughput since the last
+    /// call, newest slot, and approximate inter-arrival percentiles.
+    pub fn log_summary(&self, since: Duration) {
+        let since_secs = since.as_secs_f64().max(0.001);
+        for (stream_type, stream) in &self.streams {
+            let messages = stream.messages.load(Ordering::Relaxed);
+            let previous = stream.last_reported_messages.swap(messages, Ordering::Relaxed);
+            let newest_slot = stream.newest_slot.load(Ordering::Relaxed);
+            println!(
+                "[metrics] {stream_type}: {:.1} msg/s, newest_slot={newest_slot}, p50={}ms p90={}ms p99={}ms",
+                messages.saturating_sub(previous) as f64 / since_secs,
+                stream.percentile_ms(0.50),
+                stream.percentile_ms(0.90),
+                stream.percentile_ms(0.99),
+            );
+        }
+    }
+
+    fn render_prometheus(&self) -> String {
+        let mut out = String::new();
+        out.push_str("# HELP corecast_messages_total Messages received per stream\n");
+        out.push_str("# TYPE corecast_messages_total counter\n");
+        for (stream_type, stream) in &self.streams {
+            out.push_str(&format!(
+                "corecast_messages_total{{stream=\"{stream_type}\"}} {}\n",
+                stream.messages.load(Ordering::Relaxed)
+            ));
+        }
+        out.push_str("# HELP corecast_newest_slot Newest slot seen per stream\n");
+        out.push_str("# TYPE corecast_newest_slot gauge\n");
+        for (stream_type, stream) in &self.streams {
+            out.push_str(&format!(
+                "corecast_newest_slot{{stream=\"{stream_type}\"}} {}\n",
+                stream.newest_slot.load(Ordering::Relaxed)
+            ));
+        }
+        out.push_str("# HELP corecast_message_gap_ms Inter-message gap, in milliseconds\n");
+        out.push_str("# TYPE corecast_message_gap_ms histogram\n");
+        for (stream_type, stream) in &self.streams {
+            let mut cumulative = 0u64;
+            for (i, bound) in BUCKET_BOUNDS_MS.iter().enumerate() {
+                cumulative += stream.histogram[i].load(Ordering::Relaxed);
+                out.push_str(&format!(
+                    "corecast_message_gap_ms_bucket{{stream=\"{stream_type}\",le=\"{bound}\"}} {cumulative}\n"
+                ));
+            }
+            cumulative += stream.histogram[BUCKET_BOUNDS_MS.len()].load(Ordering::Relaxed);
+            out.push_str(&format!(
+                "corecast_message_gap_ms_bucket{{stream=\"{stream_type}\",le=\"+Inf\"}} {cumulative}\n"
+            ));
+            out.push_str(&format!(
+                "corecast_message_gap_ms_count{{stream=\"{stream_type}\"}} {cumulative}\n"
+            ));
+        }
+        out
+    }
+}
+
+/// Spawns the periodic `[metrics]` summary logger. Runs until the process
+/// exits; there's no stream-level "done" signal to cancel it on.
+pub fn spawn_periodic_logger(metrics: Arc<Metrics>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut last = Instant::now();
+        loop {
+            tokio::time::sleep(interval).await;
+            let now = Instant::now();
+            metrics.log_summary(now.duration_since(last));
+            last = now;
+        }
+    });
+}
+
+/// Serves Prometheus text-format metrics on `listen` (e.g. "0.0.0.0:9090").
+/// Hand-rolled rather than pulling in a web framework: the sample only ever
+/// needs to answer every request with the same plaintext body.
+pub async fn serve_http(metrics: Arc<Metrics>, listen: String) -> std::io::Result<()> {
+    let listener = TcpListener::bind(&listen).await?;
+    println!("[metrics] serving Prometheus metrics on http://{listen}/metrics");
+    loop {
+        let (mut socket, _) = listener.accept().await?;
+        let metrics = metrics.clone();
+        tokio::spawn(async move {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+            let mut buf = [0u8; 1024];
+            // We don't care what was requested; drain it and always answer
+            // with the current snapshot.
+            let _ = socket.read(&mut buf).await;
+            let body = metrics.render_prometheus();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+        });
+    }
+}
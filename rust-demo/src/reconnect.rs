@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tokio::sync::mpsc;
+use tonic::transport::Channel;
+
+use crate::idl::Idl;
+use crate::metrics::Metrics;
+use crate::solana_corecast::core_cast_client::CoreCastClient;
+use crate::{
+    build_channel, stream_balances, stream_dex_orders, stream_dex_pools, stream_dex_trades,
+    stream_transactions, stream_transfers, Config, TaggedEvent,
+};
+
+/// Bounds for the reconnect/backoff loop in [`run_with_resume`]. A connector
+/// that dies before sending a single message doesn't get faster retries than
+/// one that streamed for an hour first: backoff only resets once we've
+/// actually made forward progress (seen a new slot).
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct ReconnectConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    /// 0 means retry forever.
+    #[serde(default)]
+    pub max_retries: u32,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_initial_backoff_ms() -> u64 {
+    500
+}
+
+fn default_max_backoff_ms() -> u64 {
+    30_000
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        ReconnectConfig {
+            enabled: default_enabled(),
+            initial_backoff_ms: default_initial_backoff_ms(),
+            max_backoff_ms: default_max_backoff_ms(),
+            max_retries: 0,
+        }
+    }
+}
+
+/// Runs `stream_type` against `server_url` until `reconnect.max_retries` is
+/// exhausted (or forever, if it's 0). On a transport error or a server-side
+/// stream end, the channel is reconnected and the subscription is re-sent
+/// with `from_slot` set to just past the highest slot we've already seen, so
+/// the server replays the gap instead of resuming from live tip.
+pub async fn run_with_resume(
+    stream_type: &str,
+    server_url: &str,
+    config: &Arc<Config>,
+    tx: mpsc::Sender<TaggedEvent>,
+    metrics: Arc<Metrics>,
+    idls: Arc<HashMap<String, Idl>>,
+) -> Result<(), Box<dyn Error>> {
+    let reconnect = config.reconnect.clone().unwrap_or_default();
+    let last_slot = AtomicU64::new(0);
+    let mut backoff_ms = reconnect.initial_backoff_ms;
+    let mut attempt: u32 = 0;
+
+    loop {
+        let channel = match build_channel(server_url).await {
+            Ok(channel) => channel,
+            Err(err) => {
+                if !reconnect.enabled || (reconnect.max_retries != 0 && attempt >= reconnect.max_retries)
+                {
+                    return Err(err);
+                }
+                eprintln!("[{stream_type}] failed to connect: {err}. Retrying in {backoff_ms}ms...");
+                attempt += 1;
+                sleep_with_jitter(backoff_ms).await;
+                backoff_ms = next_backoff(backoff_ms, reconnect.max_backoff_ms);
+                continue;
+            }
+        };
+
+        let mut client = CoreCastClient::new(channel);
+        let seen_slot = last_slot.load(Ordering::Relaxed);
+        let from_slot = if seen_slot > 0 { seen_slot.saturating_add(1) } else { 0 };
+        if from_slot > 0 {
+            println!("[{stream_type}] resuming from slot {from_slot}");
+        }
+
+        let result = dispatch(stream_type, &mut client, config, from_slot, &last_slot, &tx, &metrics, &idls).await;
+
+        if !reconnect.enabled {
+            return result;
+        }
+
+        match &result {
+            Ok(()) => eprintln!("[{stream_type}] stream ended, reconnecting..."),
+            Err(err) => eprintln!("[{stream_type}] stream error: {err}. Reconnecting..."),
+        }
+
+        if last_slot.load(Ordering::Relaxed) > seen_slot {
+            // Made progress since the last reconnect; don't carry the
+            // backoff penalty from an earlier, unrelated failure.
+            backoff_ms = reconnect.initial_backoff_ms;
+            attempt = 0;
+        }
+
+        if reconnect.max_retries != 0 && attempt >= reconnect.max_retries {
+            return result.and(Err(format!(
+                "[{stream_type}] exceeded max_retries ({})",
+                reconnect.max_retries
+            )
+            .into()));
+        }
+        attempt += 1;
+        sleep_with_jitter(backoff_ms).await;
+        backoff_ms = next_backoff(backoff_ms, reconnect.max_backoff_ms);
+    }
+}
+
+async fn dispatch(
+    stream_type: &str,
+    client: &mut CoreCastClient<Channel>,
+    config: &Config,
+    from_slot: u64,
+    last_slot: &AtomicU64,
+    tx: &mpsc::Sender<TaggedEvent>,
+    metrics: &Arc<Metrics>,
+    idls: &Arc<HashMap<String, Idl>>,
+) -> Result<(), Box<dyn Error>> {
+    match stream_type {
+        "dex_trades" => stream_dex_trades(client, config, from_slot, last_slot, tx, metrics).await,
+        "dex_orders" => stream_dex_orders(client, config, from_slot, last_slot, tx, metrics).await,
+        "dex_pools" => stream_dex_pools(client, config, from_slot, last_slot, tx, metrics).await,
+        "transactions" => stream_transactions(client, config, from_slot, last_slot, tx, metrics, idls).await,
+        "transfers" => stream_transfers(client, config, from_slot, last_slot, tx, metrics).await,
+        "balances" => stream_balances(client, config, from_slot, last_slot, tx, metrics).await,
+        other => Err(format!("Unknown stream type: {other}").into()),
+    }
+}
+
+fn next_backoff(current_ms: u64, cap_ms: u64) -> u64 {
+    current_ms.saturating_mul(2).min(cap_ms)
+}
+
+async fn sleep_with_jitter(base_ms: u64) {
+    let jitter_ms = pseudo_random(base_ms / 4 + 1);
+    tokio::time::sleep(Duration::from_millis(base_ms + jitter_ms)).await;
+}
+
+/// Cheap jitter source: we don't need cryptographic randomness, just enough
+/// spread to stop many reconnecting clients from retrying in lockstep.
+fn pseudo_random(bound: u64) -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64)
+        .unwrap_or(0);
+    if bound == 0 {
+        0
+    } else {
+        nanos % bound
+    }
+}